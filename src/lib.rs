@@ -16,10 +16,58 @@ extern crate futures_cpupool;
 extern crate num_cpus;
 
 use std::collections::VecDeque;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use futures::Future;
 use futures_cpupool::{CpuPool, CpuFuture};
 
+/// A thread pool shared across one or several parallel adaptors.
+///
+/// By default, `par_map`/`par_flat_map` (and the other adaptors
+/// without a `_with` suffix) all route their work through a single,
+/// lazily-initialized, process-wide pool of `num_cpus::get()` threads,
+/// so chaining several adaptors does not oversubscribe the machine.
+/// Build a `ParMapPool` yourself, and pass it to the `_with` variants
+/// of the adaptors (`par_map_with`, `par_flat_map_with`, ...), only
+/// when you need an *additional* pool that is independent from that
+/// default one -- for instance to give one particular chain its own
+/// bounded thread budget.
+///
+/// # Example
+///
+/// ```
+/// use par_map::{ParMap, ParMapPool};
+/// let pool = ParMapPool::new(4);
+/// let a = [1, 2, 3];
+/// let mut iter = a.iter()
+///     .cloned()
+///     .par_map_with(&pool, |x| 2 * x)
+///     .par_flat_map_with(&pool, |x| vec![x, x]);
+/// assert_eq!(iter.next(), Some(2));
+/// assert_eq!(iter.next(), Some(2));
+/// assert_eq!(iter.next(), Some(4));
+/// ```
+#[derive(Clone)]
+pub struct ParMapPool {
+    pool: CpuPool,
+    nb_threads: usize,
+}
+impl ParMapPool {
+    /// Creates a pool of `nb_threads` worker threads.
+    pub fn new(nb_threads: usize) -> Self {
+        ParMapPool {
+            pool: CpuPool::new(nb_threads),
+            nb_threads: nb_threads,
+        }
+    }
+}
+
+/// Returns the process-wide default pool, created on first use with
+/// `num_cpus::get()` threads.
+fn default_pool() -> ParMapPool {
+    static POOL: OnceLock<ParMapPool> = OnceLock::new();
+    POOL.get_or_init(|| ParMapPool::new(num_cpus::get())).clone()
+}
+
 /// This trait extends `std::iter::Iterator` with parallel
 /// iterator adaptors.  Just `use` it to get access to the methods:
 ///
@@ -27,14 +75,17 @@ use futures_cpupool::{CpuPool, CpuFuture};
 /// use par_map::ParMap;
 /// ```
 ///
-/// Each iterator adaptor will have its own thread pool of the number
-/// of CPU.  At maximum, 2 times the number of CPU tasks will be
-/// launched in advance, guarantying that the memory will not be
+/// Unless a `ParMapPool` is explicitly given (see the `_with`
+/// variants), all of these adaptors route through one shared,
+/// lazily-initialized thread pool sized to the number of CPU (see
+/// `default_pool`).  At maximum, 2 times the number of CPU tasks will
+/// be in flight at once, guarantying that the memory will not be
 /// exceeded if the iterator is not consumed faster that the
-/// production.  To be effective, the given function should be costy
-/// to compute and each call should take about the same time.  The
-/// `packed` variants will do the same, processing by batch instead of
-/// doing one job for each item.
+/// production; this can be tuned with `with_buffer`.  To be
+/// effective, the given function should be costy to compute and each
+/// call should take about the same time.  The `packed` variants will
+/// do the same, processing by batch instead of doing one job for each
+/// item.
 ///
 /// The `'static` constraints are needed to have such a simple
 /// interface.  These adaptors are well suited for big iterators that
@@ -66,17 +117,39 @@ pub trait ParMap: Iterator + Sized {
         B: Send + 'static,
         Self::Item: Send + 'static,
     {
-        let num_threads = num_cpus::get();
-        let mut res = Map {
-            pool: CpuPool::new(num_threads),
+        self.par_map_with(&default_pool(), f)
+    }
+
+    /// Same as `par_map`, but runs on the given `pool` instead of the
+    /// process-wide default one.  This lets a whole adaptor chain,
+    /// or several independent chains, share one bounded set of
+    /// threads.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::{ParMap, ParMapPool};
+    /// let pool = ParMapPool::new(4);
+    /// let a = [1, 2, 3];
+    /// let mut iter = a.iter().cloned().par_map_with(&pool, |x| 2 * x);
+    /// assert_eq!(iter.next(), Some(2));
+    /// assert_eq!(iter.next(), Some(4));
+    /// assert_eq!(iter.next(), Some(6));
+    /// assert_eq!(iter.next(), None);
+    /// ```
+    fn par_map_with<B, F>(self, pool: &ParMapPool, f: F) -> Map<Self, B, F>
+    where
+        F: Sync + Send + 'static + Fn(Self::Item) -> B,
+        B: Send + 'static,
+        Self::Item: Send + 'static,
+    {
+        Map {
+            pool: pool.pool.clone(),
             queue: VecDeque::new(),
             iter: self,
             f: Arc::new(f),
-        };
-        for _ in 0..num_threads * 2 {
-            res.spawn();
+            in_flight: pool.nb_threads * 2,
         }
-        res
     }
 
     /// Creates an iterator that works like map, but flattens nested
@@ -104,18 +177,39 @@ pub trait ParMap: Iterator + Sized {
         U::Item: Send + 'static,
         Self::Item: Send + 'static,
     {
-        let num_threads = num_cpus::get();
-        let mut res = FlatMap {
-            pool: CpuPool::new(num_threads),
+        self.par_flat_map_with(&default_pool(), f)
+    }
+
+    /// Same as `par_flat_map`, but runs on the given `pool` instead of
+    /// the process-wide default one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::{ParMap, ParMapPool};
+    /// let pool = ParMapPool::new(4);
+    /// let words = ["alpha", "beta", "gamma"];
+    /// let merged: String = words.iter()
+    ///     .cloned()
+    ///     .par_flat_map_with(&pool, |s| s.chars())
+    ///     .collect();
+    /// assert_eq!(merged, "alphabetagamma");
+    /// ```
+    fn par_flat_map_with<U, F>(self, pool: &ParMapPool, f: F) -> FlatMap<Self, U, F>
+    where
+        F: Sync + Send + 'static + Fn(Self::Item) -> U,
+        U: IntoIterator,
+        U::Item: Send + 'static,
+        Self::Item: Send + 'static,
+    {
+        FlatMap {
+            pool: pool.pool.clone(),
             queue: VecDeque::new(),
             iter: self,
             f: Arc::new(f),
             cur_iter: vec![].into_iter(),
-        };
-        for _ in 0..num_threads * 2 {
-            res.spawn();
+            in_flight: pool.nb_threads * 2,
         }
-        res
     }
 
     /// Creates an iterator that yields `Vec<Self::Item>` of size `nb`
@@ -192,6 +286,405 @@ pub trait ParMap: Iterator + Sized {
         };
         Box::new(self.pack(nb).par_flat_map(f))
     }
+
+    /// Creates an iterator which uses `predicate` to determine whether
+    /// an element should be yielded, exactly as
+    /// `std::iter::Iterator::filter`, but evaluating `predicate` in
+    /// parallel.
+    ///
+    /// The order of the elements are guaranted to be unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::ParMap;
+    /// let a = [1, 2, 3, 4];
+    /// let mut iter = a.iter().cloned().par_filter(|x| x % 2 == 0);
+    /// assert_eq!(iter.next(), Some(2));
+    /// assert_eq!(iter.next(), Some(4));
+    /// assert_eq!(iter.next(), None);
+    /// ```
+    fn par_filter<'a, P>(self, predicate: P) -> Box<Iterator<Item = Self::Item> + 'a>
+    where
+        P: Sync + Send + 'static + Fn(&Self::Item) -> bool,
+        Self::Item: Send + 'static,
+        Self: 'a,
+    {
+        let f = move |item: Self::Item| if predicate(&item) { Some(item) } else { None };
+        Box::new(self.par_flat_map(f))
+    }
+
+    /// Creates an iterator that both filters and maps, exactly as
+    /// `std::iter::Iterator::filter_map`, but evaluating `f` in
+    /// parallel.
+    ///
+    /// The order of the elements are guaranted to be unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::ParMap;
+    /// let a = ["1", "two", "3"];
+    /// let mut iter = a.iter().cloned().par_filter_map(|x| x.parse::<i32>().ok());
+    /// assert_eq!(iter.next(), Some(1));
+    /// assert_eq!(iter.next(), Some(3));
+    /// assert_eq!(iter.next(), None);
+    /// ```
+    fn par_filter_map<'a, B, F>(self, f: F) -> Box<Iterator<Item = B> + 'a>
+    where
+        F: Sync + Send + 'static + Fn(Self::Item) -> Option<B>,
+        B: Send + 'static,
+        Self::Item: Send + 'static,
+        Self: 'a,
+    {
+        Box::new(self.par_flat_map(f))
+    }
+
+    /// Calls `f` on each element in parallel, blocking until every
+    /// call has completed.  Exactly as `std::iter::Iterator::for_each`,
+    /// but `f` is dispatched to the thread pool instead of being run
+    /// in sequence.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use par_map::ParMap;
+    /// let sum = Arc::new(AtomicUsize::new(0));
+    /// let sum2 = sum.clone();
+    /// [1, 2, 3].iter().cloned().par_for_each(move |x| {
+    ///     sum2.fetch_add(x, Ordering::SeqCst);
+    /// });
+    /// assert_eq!(sum.load(Ordering::SeqCst), 6);
+    /// ```
+    fn par_for_each<F>(self, f: F)
+    where
+        F: Sync + Send + 'static + Fn(Self::Item),
+        Self::Item: Send + 'static,
+    {
+        for _ in self.par_map(f) {}
+    }
+
+    /// Folds every element into an accumulator, exactly as
+    /// `std::iter::Iterator::fold`.
+    ///
+    /// This is a terminal consumer: it is meant to be called on an
+    /// iterator produced upstream by `par_map`/`par_flat_map`, whose
+    /// per-item work already runs in parallel.  The fold itself
+    /// combines the (already computed) results in input order, one at
+    /// a time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::ParMap;
+    /// let a = [1, 2, 3];
+    /// let total = a.iter().cloned().par_map(|x| x * x).par_fold(0, |acc, x| acc + x);
+    /// assert_eq!(total, 14);
+    /// ```
+    fn par_fold<Acc, Op>(self, init: Acc, op: Op) -> Acc
+    where
+        Op: Fn(Acc, Self::Item) -> Acc,
+    {
+        self.fold(init, op)
+    }
+
+    /// Combines every element with `identity` and `op`, exactly as
+    /// `std::iter::Iterator::fold` with the identity as the initial
+    /// accumulator.
+    ///
+    /// Partial results are combined in input order (`op(acc, next)`,
+    /// draining the elements front-to-back), so the result is
+    /// deterministic regardless of the order in which the underlying
+    /// parallel tasks complete; only the per-item work, computed
+    /// upstream, runs in parallel.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::ParMap;
+    /// let a = [1, 2, 3];
+    /// let total = a.iter().cloned().par_map(|x| x * x).par_reduce(0, |acc, x| acc + x);
+    /// assert_eq!(total, 14);
+    /// ```
+    fn par_reduce<Op>(self, identity: Self::Item, op: Op) -> Self::Item
+    where
+        Op: Fn(Self::Item, Self::Item) -> Self::Item,
+    {
+        self.fold(identity, op)
+    }
+
+    /// Sums every element, exactly as `std::iter::Iterator::sum`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::ParMap;
+    /// let a = [1, 2, 3];
+    /// let total: i32 = a.iter().cloned().par_map(|x| x * x).par_sum();
+    /// assert_eq!(total, 14);
+    /// ```
+    fn par_sum<S>(self) -> S
+    where
+        S: ::std::iter::Sum<Self::Item>,
+    {
+        self.sum()
+    }
+
+    /// Counts the elements, exactly as `std::iter::Iterator::count`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::ParMap;
+    /// let a = [1, 2, 3];
+    /// let n = a.iter().cloned().par_map(|x| x * x).par_count();
+    /// assert_eq!(n, 3);
+    /// ```
+    fn par_count(self) -> usize {
+        self.count()
+    }
+
+    /// Same as `par_map`, but elements are yielded in completion
+    /// order instead of input order: as soon as any in-flight task
+    /// finishes, its result is returned, and a new task is spawned to
+    /// keep the number of tasks in flight at its cap (twice the pool's
+    /// thread count, unless changed with `with_buffer`).
+    ///
+    /// This trades order for throughput: a single slow item no longer
+    /// stalls the faster results queued behind it.  **Element order is
+    /// not preserved** — use `par_map` if that matters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::ParMap;
+    /// let a = [1, 2, 3];
+    /// let mut result: Vec<_> = a.iter().cloned().par_map_unordered(|x| 2 * x).collect();
+    /// result.sort();
+    /// assert_eq!(result, vec![2, 4, 6]);
+    /// ```
+    fn par_map_unordered<B, F>(self, f: F) -> MapUnordered<Self, B, F>
+    where
+        F: Sync + Send + 'static + Fn(Self::Item) -> B,
+        B: Send + 'static,
+        Self::Item: Send + 'static,
+    {
+        self.par_map_unordered_with(&default_pool(), f)
+    }
+
+    /// Same as `par_map_unordered`, but runs on the given `pool`
+    /// instead of the process-wide default one.  See `par_map_with`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::{ParMap, ParMapPool};
+    /// let pool = ParMapPool::new(4);
+    /// let a = [1, 2, 3];
+    /// let mut result: Vec<_> = a.iter().cloned().par_map_unordered_with(&pool, |x| 2 * x).collect();
+    /// result.sort();
+    /// assert_eq!(result, vec![2, 4, 6]);
+    /// ```
+    fn par_map_unordered_with<B, F>(self, pool: &ParMapPool, f: F) -> MapUnordered<Self, B, F>
+    where
+        F: Sync + Send + 'static + Fn(Self::Item) -> B,
+        B: Send + 'static,
+        Self::Item: Send + 'static,
+    {
+        MapUnordered {
+            pool: pool.pool.clone(),
+            futures: Vec::new(),
+            iter: self,
+            f: Arc::new(f),
+            in_flight: pool.nb_threads * 2,
+        }
+    }
+
+    /// Same as `par_flat_map`, but elements are yielded in completion
+    /// order instead of input order.  See `par_map_unordered` for
+    /// details; **element order is not preserved**.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::ParMap;
+    /// let words = ["alpha", "beta", "gamma"];
+    /// let mut chars: Vec<_> = words.iter()
+    ///     .cloned()
+    ///     .par_flat_map_unordered(|s| s.chars())
+    ///     .collect();
+    /// chars.sort();
+    /// let mut expected: Vec<_> = "alphabetagamma".chars().collect();
+    /// expected.sort();
+    /// assert_eq!(chars, expected);
+    /// ```
+    fn par_flat_map_unordered<U, F>(self, f: F) -> FlatMapUnordered<Self, U, F>
+    where
+        F: Sync + Send + 'static + Fn(Self::Item) -> U,
+        U: IntoIterator,
+        U::Item: Send + 'static,
+        Self::Item: Send + 'static,
+    {
+        self.par_flat_map_unordered_with(&default_pool(), f)
+    }
+
+    /// Same as `par_flat_map_unordered`, but runs on the given `pool`
+    /// instead of the process-wide default one.  See `par_flat_map_with`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::{ParMap, ParMapPool};
+    /// let pool = ParMapPool::new(4);
+    /// let words = ["alpha", "beta", "gamma"];
+    /// let mut chars: Vec<_> = words.iter()
+    ///     .cloned()
+    ///     .par_flat_map_unordered_with(&pool, |s| s.chars())
+    ///     .collect();
+    /// chars.sort();
+    /// let mut expected: Vec<_> = "alphabetagamma".chars().collect();
+    /// expected.sort();
+    /// assert_eq!(chars, expected);
+    /// ```
+    fn par_flat_map_unordered_with<U, F>(self, pool: &ParMapPool, f: F) -> FlatMapUnordered<Self, U, F>
+    where
+        F: Sync + Send + 'static + Fn(Self::Item) -> U,
+        U: IntoIterator,
+        U::Item: Send + 'static,
+        Self::Item: Send + 'static,
+    {
+        FlatMapUnordered {
+            pool: pool.pool.clone(),
+            futures: Vec::new(),
+            iter: self,
+            f: Arc::new(f),
+            cur_iter: vec![].into_iter(),
+            in_flight: pool.nb_threads * 2,
+        }
+    }
+
+    /// Same as `par_map`, but `f` may fail: the closure returns a
+    /// `Result<B, E>` and the iterator yields `Result<B, E>` in input
+    /// order instead of panicking on the first error.
+    ///
+    /// This lets a parallel stage wrap fallible work (IO, parsing...)
+    /// and the whole pipeline be short-circuited with
+    /// `collect::<Result<Vec<_>, _>>()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::ParMap;
+    /// let a = ["1", "two", "3"];
+    /// let result: Result<Vec<i32>, _> = a.iter()
+    ///     .cloned()
+    ///     .try_par_map(|x| x.parse::<i32>())
+    ///     .collect();
+    /// assert!(result.is_err());
+    /// ```
+    fn try_par_map<B, E, F>(self, f: F) -> TryMap<Self, B, E, F>
+    where
+        F: Sync + Send + 'static + Fn(Self::Item) -> Result<B, E>,
+        B: Send + 'static,
+        E: Send + 'static,
+        Self::Item: Send + 'static,
+    {
+        self.try_par_map_with(&default_pool(), f)
+    }
+
+    /// Same as `try_par_map`, but runs on the given `pool` instead of
+    /// the process-wide default one.  See `par_map_with`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::{ParMap, ParMapPool};
+    /// let pool = ParMapPool::new(4);
+    /// let a = ["1", "two", "3"];
+    /// let result: Result<Vec<i32>, _> = a.iter()
+    ///     .cloned()
+    ///     .try_par_map_with(&pool, |x| x.parse::<i32>())
+    ///     .collect();
+    /// assert!(result.is_err());
+    /// ```
+    fn try_par_map_with<B, E, F>(self, pool: &ParMapPool, f: F) -> TryMap<Self, B, E, F>
+    where
+        F: Sync + Send + 'static + Fn(Self::Item) -> Result<B, E>,
+        B: Send + 'static,
+        E: Send + 'static,
+        Self::Item: Send + 'static,
+    {
+        TryMap {
+            pool: pool.pool.clone(),
+            queue: VecDeque::new(),
+            iter: self,
+            f: Arc::new(f),
+            in_flight: pool.nb_threads * 2,
+        }
+    }
+
+    /// Same as `try_par_map`, but the parallel work is batched by `nb`
+    /// items, exactly as `par_packed_map` batches `par_map`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::ParMap;
+    /// let a = ["1", "2", "3"];
+    /// let result: Result<Vec<i32>, _> = a.iter()
+    ///     .cloned()
+    ///     .try_par_packed_map(2, |x| x.parse::<i32>())
+    ///     .collect();
+    /// assert_eq!(result, Ok(vec![1, 2, 3]));
+    /// ```
+    fn try_par_packed_map<'a, B, E, F>(self, nb: usize, f: F) -> Box<Iterator<Item = Result<B, E>> + 'a>
+    where
+        F: Sync + Send + 'static + Fn(Self::Item) -> Result<B, E>,
+        B: Send + 'static,
+        E: Send + 'static,
+        Self::Item: Send + 'static,
+        Self: 'a,
+    {
+        self.try_par_packed_map_with(&default_pool(), nb, f)
+    }
+
+    /// Same as `try_par_packed_map`, but runs on the given `pool`
+    /// instead of the process-wide default one.  See `par_map_with`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::{ParMap, ParMapPool};
+    /// let pool = ParMapPool::new(4);
+    /// let a = ["1", "2", "3"];
+    /// let result: Result<Vec<i32>, _> = a.iter()
+    ///     .cloned()
+    ///     .try_par_packed_map_with(&pool, 2, |x| x.parse::<i32>())
+    ///     .collect();
+    /// assert_eq!(result, Ok(vec![1, 2, 3]));
+    /// ```
+    fn try_par_packed_map_with<'a, B, E, F>(
+        self,
+        pool: &ParMapPool,
+        nb: usize,
+        f: F,
+    ) -> Box<Iterator<Item = Result<B, E>> + 'a>
+    where
+        F: Sync + Send + 'static + Fn(Self::Item) -> Result<B, E>,
+        B: Send + 'static,
+        E: Send + 'static,
+        Self::Item: Send + 'static,
+        Self: 'a,
+    {
+        let f = Arc::new(f);
+        let f = move |iter: Vec<Self::Item>| {
+            let f = f.clone();
+            iter.into_iter().map(move |i| f(i))
+        };
+        Box::new(self.pack(nb).par_flat_map_with(pool, f))
+    }
 }
 impl<I: Iterator> ParMap for I {}
 
@@ -205,6 +698,7 @@ pub struct Map<I, B, F> {
     queue: VecDeque<CpuFuture<B, ()>>,
     iter: I,
     f: Arc<F>,
+    in_flight: usize,
 }
 impl<I: Iterator, B: Send + 'static, F> Map<I, B, F>
 where
@@ -212,6 +706,9 @@ where
     I::Item: Send + 'static,
 {
     fn spawn(&mut self) {
+        if self.queue.len() >= self.in_flight {
+            return;
+        }
         let future = match self.iter.next() {
             None => return,
             Some(item) => {
@@ -221,6 +718,86 @@ where
         };
         self.queue.push_back(future);
     }
+
+    /// Tops up the queue to `in_flight`, spawning one task at a time
+    /// until the cap is reached or `iter` is exhausted.  Called lazily
+    /// from `next()` rather than eagerly at construction time, so that
+    /// a `with_buffer()` call made before the first `next()` bounds
+    /// the very first batch of spawned tasks, not just subsequent
+    /// ones.
+    fn fill(&mut self) {
+        while self.queue.len() < self.in_flight {
+            let len_before = self.queue.len();
+            self.spawn();
+            if self.queue.len() == len_before {
+                break;
+            }
+        }
+    }
+
+    /// Overrides the maximum number of in-flight (spawned but not yet
+    /// drained) tasks, which otherwise defaults to twice the pool's
+    /// thread count.  This bounds memory use independently of the
+    /// number of worker threads, which matters when items or their
+    /// mapped results are large or of uneven size.
+    ///
+    /// `in_flight` is clamped to at least `1`: a cap of `0` would mean
+    /// no task may ever be spawned again once the (already produced)
+    /// in-flight futures are drained, silently dropping the rest of
+    /// the input instead of just throttling it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::ParMap;
+    /// let a = [1, 2, 3];
+    /// let mut iter = a.iter().cloned().par_map(|x| 2 * x).with_buffer(1);
+    /// assert_eq!(iter.next(), Some(2));
+    /// assert_eq!(iter.next(), Some(4));
+    /// assert_eq!(iter.next(), Some(6));
+    /// assert_eq!(iter.next(), None);
+    /// ```
+    ///
+    /// The cap is enforced from the very first task spawned, not just
+    /// once the iterator is already under way: even the initial
+    /// lookahead never exceeds `in_flight` tasks, regardless of the
+    /// pool's thread count.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use par_map::{ParMap, ParMapPool};
+    ///
+    /// let pool = ParMapPool::new(8);
+    /// let current = Arc::new(AtomicUsize::new(0));
+    /// let peak = Arc::new(AtomicUsize::new(0));
+    /// let (c, p) = (current.clone(), peak.clone());
+    /// let result: Vec<_> = (0..6)
+    ///     .par_map_with(&pool, move |x| {
+    ///         let n = c.fetch_add(1, Ordering::SeqCst) + 1;
+    ///         let mut prev = p.load(Ordering::SeqCst);
+    ///         while n > prev {
+    ///             match p.compare_exchange(prev, n, Ordering::SeqCst, Ordering::SeqCst) {
+    ///                 Ok(_) => break,
+    ///                 Err(cur) => prev = cur,
+    ///             }
+    ///         }
+    ///         thread::sleep(Duration::from_millis(20));
+    ///         c.fetch_sub(1, Ordering::SeqCst);
+    ///         x
+    ///     })
+    ///     .with_buffer(2)
+    ///     .collect();
+    /// assert_eq!(result, vec![0, 1, 2, 3, 4, 5]);
+    /// let observed_peak = peak.load(Ordering::SeqCst);
+    /// assert!(observed_peak <= 2, "peak in-flight {} exceeded buffer of 2", observed_peak);
+    /// ```
+    pub fn with_buffer(mut self, in_flight: usize) -> Self {
+        self.in_flight = ::std::cmp::max(1, in_flight);
+        self
+    }
 }
 impl<I: Iterator, B: Send + 'static, F> Iterator for Map<I, B, F>
 where
@@ -232,6 +809,7 @@ where
 {
     type Item = B;
     fn next(&mut self) -> Option<Self::Item> {
+        self.fill();
         self.queue.pop_front().map(|future| {
             let i = future.wait().unwrap();
             self.spawn();
@@ -240,6 +818,76 @@ where
     }
 }
 
+/// An iterator that maps the values of `iter` with the fallible `f`,
+/// yielding `Result<B, E>` for each element.
+///
+/// This struct is created by the `try_par_map()` method on `ParMap`.
+/// See its documentation for more.
+#[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
+pub struct TryMap<I, B, E, F> {
+    pool: CpuPool,
+    queue: VecDeque<CpuFuture<B, E>>,
+    iter: I,
+    f: Arc<F>,
+    in_flight: usize,
+}
+impl<I: Iterator, B: Send + 'static, E: Send + 'static, F> TryMap<I, B, E, F>
+where
+    F: Sync + Send + 'static + Fn(I::Item) -> Result<B, E>,
+    I::Item: Send + 'static,
+{
+    fn spawn(&mut self) {
+        if self.queue.len() >= self.in_flight {
+            return;
+        }
+        let future = match self.iter.next() {
+            None => return,
+            Some(item) => {
+                let f = self.f.clone();
+                self.pool.spawn_fn(move || f(item))
+            }
+        };
+        self.queue.push_back(future);
+    }
+
+    /// Tops up the queue to `in_flight`.  See `Map::fill` for why this
+    /// is called lazily from `next()` instead of eagerly at
+    /// construction time.
+    fn fill(&mut self) {
+        while self.queue.len() < self.in_flight {
+            let len_before = self.queue.len();
+            self.spawn();
+            if self.queue.len() == len_before {
+                break;
+            }
+        }
+    }
+
+    /// Overrides the maximum number of in-flight (spawned but not yet
+    /// drained) tasks, which otherwise defaults to twice the pool's
+    /// thread count.  See `Map::with_buffer` for details, including
+    /// why `in_flight` is clamped to at least `1`.
+    pub fn with_buffer(mut self, in_flight: usize) -> Self {
+        self.in_flight = ::std::cmp::max(1, in_flight);
+        self
+    }
+}
+impl<I: Iterator, B: Send + 'static, E: Send + 'static, F> Iterator for TryMap<I, B, E, F>
+where
+    F: Sync + Send + 'static + Fn(I::Item) -> Result<B, E>,
+    I::Item: Send + 'static,
+{
+    type Item = Result<B, E>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.fill();
+        self.queue.pop_front().map(|future| {
+            let r = future.wait();
+            self.spawn();
+            r
+        })
+    }
+}
+
 /// An iterator that maps each element to an iterator, and yields the
 /// elements of the produced iterators.
 ///
@@ -252,6 +900,7 @@ pub struct FlatMap<I: Iterator, U: IntoIterator, F> {
     iter: I,
     f: Arc<F>,
     cur_iter: ::std::vec::IntoIter<U::Item>,
+    in_flight: usize,
 }
 impl<I: Iterator, U: IntoIterator, F> FlatMap<I, U, F>
 where
@@ -260,6 +909,9 @@ where
     I::Item: Send + 'static,
 {
     fn spawn(&mut self) {
+        if self.queue.len() >= self.in_flight {
+            return;
+        }
         let future = match self.iter.next() {
             None => return,
             Some(item) => {
@@ -271,6 +923,41 @@ where
         };
         self.queue.push_back(future);
     }
+
+    /// Tops up the queue to `in_flight`.  See `Map::fill` for why this
+    /// is called lazily from `next()` instead of eagerly at
+    /// construction time.
+    fn fill(&mut self) {
+        while self.queue.len() < self.in_flight {
+            let len_before = self.queue.len();
+            self.spawn();
+            if self.queue.len() == len_before {
+                break;
+            }
+        }
+    }
+
+    /// Overrides the maximum number of in-flight (spawned but not yet
+    /// drained) tasks, which otherwise defaults to twice the pool's
+    /// thread count.  See `Map::with_buffer` for details, including
+    /// why `in_flight` is clamped to at least `1`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use par_map::ParMap;
+    /// let words = ["alpha", "beta", "gamma"];
+    /// let merged: String = words.iter()
+    ///     .cloned()
+    ///     .par_flat_map(|s| s.chars())
+    ///     .with_buffer(1)
+    ///     .collect();
+    /// assert_eq!(merged, "alphabetagamma");
+    /// ```
+    pub fn with_buffer(mut self, in_flight: usize) -> Self {
+        self.in_flight = ::std::cmp::max(1, in_flight);
+        self
+    }
 }
 impl<I: Iterator, U: IntoIterator, F> Iterator for FlatMap<I, U, F>
 where
@@ -287,6 +974,7 @@ where
             if let Some(item) = self.cur_iter.next() {
                 return Some(item);
             }
+            self.fill();
             let v = match self.queue.pop_front() {
                 Some(future) => future.wait().unwrap(),
                 None => return None,
@@ -297,6 +985,163 @@ where
     }
 }
 
+/// An iterator that maps the values of `iter` with `f`, yielding
+/// results in completion order rather than input order.
+///
+/// This struct is created by the `par_map_unordered()` method on
+/// `ParMap`.  See its documentation for more.
+#[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
+pub struct MapUnordered<I, B, F> {
+    pool: CpuPool,
+    futures: Vec<CpuFuture<B, ()>>,
+    iter: I,
+    f: Arc<F>,
+    in_flight: usize,
+}
+impl<I: Iterator, B: Send + 'static, F> MapUnordered<I, B, F>
+where
+    F: Sync + Send + 'static + Fn(I::Item) -> B,
+    I::Item: Send + 'static,
+{
+    fn spawn(&mut self) {
+        if self.futures.len() >= self.in_flight {
+            return;
+        }
+        let future = match self.iter.next() {
+            None => return,
+            Some(item) => {
+                let f = self.f.clone();
+                self.pool.spawn_fn(move || Ok(f(item)))
+            }
+        };
+        self.futures.push(future);
+    }
+
+    /// Tops up `futures` to `in_flight`.  See `Map::fill` for why this
+    /// is called lazily from `next()` instead of eagerly at
+    /// construction time.
+    fn fill(&mut self) {
+        while self.futures.len() < self.in_flight {
+            let len_before = self.futures.len();
+            self.spawn();
+            if self.futures.len() == len_before {
+                break;
+            }
+        }
+    }
+
+    /// Overrides the maximum number of in-flight (spawned but not yet
+    /// drained) tasks, which otherwise defaults to twice the pool's
+    /// thread count.  See `Map::with_buffer` for details, including
+    /// why `in_flight` is clamped to at least `1`.
+    pub fn with_buffer(mut self, in_flight: usize) -> Self {
+        self.in_flight = ::std::cmp::max(1, in_flight);
+        self
+    }
+}
+impl<I: Iterator, B: Send + 'static, F> Iterator for MapUnordered<I, B, F>
+where
+    F: Sync + Send + 'static + Fn(I::Item) -> B,
+    I::Item: Send + 'static,
+{
+    type Item = B;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.fill();
+        if self.futures.is_empty() {
+            return None;
+        }
+        let futures = ::std::mem::replace(&mut self.futures, Vec::new());
+        let (value, _index, remaining) = ::futures::future::select_all(futures).wait().ok().unwrap();
+        self.futures = remaining;
+        self.spawn();
+        Some(value)
+    }
+}
+
+/// An iterator that maps each element to an iterator and yields the
+/// elements of the produced iterators, in completion order rather
+/// than input order.
+///
+/// This struct is created by the `par_flat_map_unordered()` method on
+/// `ParMap`.  See its documentation for more.
+#[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
+pub struct FlatMapUnordered<I: Iterator, U: IntoIterator, F> {
+    pool: CpuPool,
+    futures: Vec<CpuFuture<Vec<U::Item>, ()>>,
+    iter: I,
+    f: Arc<F>,
+    cur_iter: ::std::vec::IntoIter<U::Item>,
+    in_flight: usize,
+}
+impl<I: Iterator, U: IntoIterator, F> FlatMapUnordered<I, U, F>
+where
+    F: Sync + Send + 'static + Fn(I::Item) -> U,
+    U::Item: Send + 'static,
+    I::Item: Send + 'static,
+{
+    fn spawn(&mut self) {
+        if self.futures.len() >= self.in_flight {
+            return;
+        }
+        let future = match self.iter.next() {
+            None => return,
+            Some(item) => {
+                let f = self.f.clone();
+                self.pool.spawn_fn(
+                    move || Ok(f(item).into_iter().collect()),
+                )
+            }
+        };
+        self.futures.push(future);
+    }
+
+    /// Tops up `futures` to `in_flight`.  See `Map::fill` for why this
+    /// is called lazily from `next()` instead of eagerly at
+    /// construction time.
+    fn fill(&mut self) {
+        while self.futures.len() < self.in_flight {
+            let len_before = self.futures.len();
+            self.spawn();
+            if self.futures.len() == len_before {
+                break;
+            }
+        }
+    }
+
+    /// Overrides the maximum number of in-flight (spawned but not yet
+    /// drained) tasks, which otherwise defaults to twice the pool's
+    /// thread count.  See `Map::with_buffer` for details, including
+    /// why `in_flight` is clamped to at least `1`.
+    pub fn with_buffer(mut self, in_flight: usize) -> Self {
+        self.in_flight = ::std::cmp::max(1, in_flight);
+        self
+    }
+}
+impl<I: Iterator, U: IntoIterator, F> Iterator for FlatMapUnordered<I, U, F>
+where
+    F: Sync + Send + 'static + Fn(I::Item) -> U,
+    U::Item: Send + 'static,
+    I::Item: Send + 'static,
+{
+    type Item = U::Item;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.cur_iter.next() {
+                return Some(item);
+            }
+            self.fill();
+            if self.futures.is_empty() {
+                return None;
+            }
+            let futures = ::std::mem::replace(&mut self.futures, Vec::new());
+            let (v, _index, remaining) = ::futures::future::select_all(futures).wait().ok().unwrap();
+            self.futures = remaining;
+            self.cur_iter = v.into_iter();
+            self.spawn();
+        }
+    }
+}
+
 /// An iterator that yields `Vec<Self::Item>` of size `nb` (or less on
 /// the last element).
 ///